@@ -1,5 +1,12 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
 use std::sync::LazyLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 
 /// URL to fetch the latest Antigravity version
 const VERSION_URL: &str = "https://antigravity-auto-updater-974169037036.us-central1.run.app";
@@ -7,50 +14,489 @@ const VERSION_URL: &str = "https://antigravity-auto-updater-974169037036.us-cent
 /// Fallback version derived from Cargo.toml at compile time
 const FALLBACK_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Pre-compiled regex for version parsing (X.Y.Z pattern)
-static VERSION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"\d+\.\d+\.\d+").expect("Invalid version regex")
+/// How long a cached remote version stays valid before we bother re-fetching
+const VERSION_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Pre-compiled regex for semver parsing: `X.Y.Z` with optional `-prerelease`
+/// and `+build` tails, per the semver.org grammar.
+static SEMVER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(\d+)\.(\d+)\.(\d+)(?:-([0-9A-Za-z.-]+))?(?:\+([0-9A-Za-z.-]+))?")
+        .expect("Invalid semver regex")
 });
 
-/// Parse version from response text using pre-compiled regex
-/// Matches semver pattern: X.Y.Z (e.g., "1.15.8")
+/// A parsed semantic version: major/minor/patch plus optional prerelease and
+/// build-metadata tails (e.g. `1.2.3-rc.1+build.5`).
+///
+/// Equality compares every field, including build metadata, so that e.g.
+/// `1.15.8+foo` and `1.15.8+bar` are distinct. Ordering follows semver's
+/// precedence rules, which explicitly ignore build metadata.
+#[derive(Debug, Clone)]
+pub(crate) struct SemVer {
+    pub(crate) major: u64,
+    pub(crate) minor: u64,
+    pub(crate) patch: u64,
+    pub(crate) prerelease: Option<String>,
+    pub(crate) build: Option<String>,
+}
+
+impl fmt::Display for SemVer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if let Some(prerelease) = &self.prerelease {
+            write!(f, "-{prerelease}")?;
+        }
+        if let Some(build) = &self.build {
+            write!(f, "+{build}")?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for SemVer {
+    fn eq(&self, other: &Self) -> bool {
+        self.major == other.major
+            && self.minor == other.minor
+            && self.patch == other.patch
+            && self.prerelease == other.prerelease
+            && self.build == other.build
+    }
+}
+
+impl Eq for SemVer {}
+
+impl PartialOrd for SemVer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SemVer {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Build metadata is deliberately excluded: semver says it MUST be
+        // ignored when determining precedence.
+        self.major
+            .cmp(&other.major)
+            .then(self.minor.cmp(&other.minor))
+            .then(self.patch.cmp(&other.patch))
+            .then_with(|| compare_prerelease(&self.prerelease, &other.prerelease))
+    }
+}
+
+/// A version without a prerelease tag takes precedence over one with: e.g.
+/// `1.0.0` > `1.0.0-alpha`. Otherwise compare dot-separated identifiers
+/// left-to-right per semver's rules (numeric identifiers compare
+/// numerically and are always lower than alphanumeric ones).
+fn compare_prerelease(a: &Option<String>, b: &Option<String>) -> Ordering {
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(a), Some(b)) => {
+            let mut a_parts = a.split('.');
+            let mut b_parts = b.split('.');
+            loop {
+                match (a_parts.next(), b_parts.next()) {
+                    (None, None) => return Ordering::Equal,
+                    (None, Some(_)) => return Ordering::Less,
+                    (Some(_), None) => return Ordering::Greater,
+                    (Some(a_id), Some(b_id)) => {
+                        let ordering = match (a_id.parse::<u64>(), b_id.parse::<u64>()) {
+                            (Ok(a_num), Ok(b_num)) => a_num.cmp(&b_num),
+                            (Ok(_), Err(_)) => Ordering::Less,
+                            (Err(_), Ok(_)) => Ordering::Greater,
+                            (Err(_), Err(_)) => a_id.cmp(b_id),
+                        };
+                        if ordering != Ordering::Equal {
+                            return ordering;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Parse a full semantic version out of free-form text, capturing
+/// prerelease and build-metadata tails instead of truncating to `X.Y.Z`.
+fn parse_semver(text: &str) -> Option<SemVer> {
+    let caps = SEMVER_REGEX.captures(text)?;
+    Some(SemVer {
+        major: caps[1].parse().ok()?,
+        minor: caps[2].parse().ok()?,
+        patch: caps[3].parse().ok()?,
+        prerelease: caps.get(4).map(|m| m.as_str().to_string()),
+        build: caps.get(5).map(|m| m.as_str().to_string()),
+    })
+}
+
+/// Parse the bare `X.Y.Z` version from response text, discarding any
+/// prerelease/build tail. Kept for callers (like the User-Agent string)
+/// that only ever want the three-component version.
 fn parse_version(text: &str) -> Option<String> {
-    VERSION_REGEX.find(text).map(|m| m.as_str().to_string())
+    parse_semver(text).map(|v| format!("{}.{}.{}", v.major, v.minor, v.patch))
 }
 
 /// Version source for logging
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum VersionSource {
+    Env,
     Remote,
     CargoToml,
 }
 
-/// Fetch version from remote endpoint, with fallback to Cargo.toml
-/// Uses a separate thread to avoid blocking the main/UI thread
-fn fetch_remote_version() -> (String, VersionSource) {
-    // [Re-applied Fix] Force use of local Cargo version to ensure stability.
-    // The remote URL might return an older version string, causing "Version Not Supported" errors.
-    
-    // Fallback: Cargo.toml version (always valid at compile time)
-    (FALLBACK_VERSION.to_string(), VersionSource::CargoToml)
+impl VersionSource {
+    fn parse(name: &str) -> Option<VersionSource> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "env" => Some(VersionSource::Env),
+            "remote" => Some(VersionSource::Remote),
+            "cargo_toml" | "cargotoml" => Some(VersionSource::CargoToml),
+            _ => None,
+        }
+    }
+}
+
+/// On-disk record of the last successful remote version check, so startup
+/// never has to wait on the network to know what it last saw.
+#[derive(Debug, Serialize, Deserialize)]
+struct VersionCache {
+    version: String,
+    fetched_at: u64,
+}
+
+impl VersionCache {
+    fn is_fresh(&self) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        now.saturating_sub(self.fetched_at) < VERSION_CACHE_TTL.as_secs()
+    }
+}
+
+/// Path to the cached remote-version file under the OS cache dir
+fn cache_file_path() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("antigravity").join("version_cache.json"))
+}
+
+fn read_version_cache() -> Option<VersionCache> {
+    let path = cache_file_path()?;
+    let contents = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&contents).ok()
+}
+
+fn write_version_cache(version: &str) {
+    let Some(path) = cache_file_path() else {
+        return;
+    };
+    let Some(parent) = path.parent() else {
+        return;
+    };
+    if fs::create_dir_all(parent).is_err() {
+        return;
+    }
+
+    let entry = VersionCache {
+        version: version.to_string(),
+        fetched_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+    if let Ok(json) = serde_json::to_string(&entry) {
+        let _ = fs::write(path, json);
+    }
+}
+
+/// Environment variable that forces offline mode, skipping all network
+/// version probing. Useful for sandboxed/air-gapped environments.
+const OFFLINE_ENV_VAR: &str = "ANTIGRAVITY_OFFLINE";
+
+/// Whether offline mode is enabled via `ANTIGRAVITY_OFFLINE`.
+fn is_offline() -> bool {
+    std::env::var(OFFLINE_ENV_VAR)
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false)
+}
+
+/// Inclusive range of remote versions this build knows how to talk to,
+/// mirroring MSRV-style gating: a declared minimum (and optional maximum)
+/// compared against a discovered version.
+#[derive(Debug, Clone)]
+struct SupportedVersionRange {
+    min: SemVer,
+    max: Option<SemVer>,
+}
+
+impl SupportedVersionRange {
+    fn validate(&self, version: &SemVer) -> Result<(), VersionValidationError> {
+        if version < &self.min {
+            return Err(VersionValidationError::TooOld {
+                found: Box::new(version.clone()),
+                min: Box::new(self.min.clone()),
+            });
+        }
+        if let Some(max) = &self.max {
+            if version > max {
+                return Err(VersionValidationError::TooNew {
+                    found: Box::new(version.clone()),
+                    max: Box::new(max.clone()),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a discovered remote version was rejected. The `SemVer` fields are
+/// boxed to keep this error small (`SemVer` carries two `Option<String>`s),
+/// so `Result<_, VersionValidationError>` doesn't trip clippy's
+/// `result_large_err`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum VersionValidationError {
+    TooOld {
+        found: Box<SemVer>,
+        min: Box<SemVer>,
+    },
+    TooNew {
+        found: Box<SemVer>,
+        max: Box<SemVer>,
+    },
+    Unparseable {
+        text: String,
+    },
+}
+
+impl fmt::Display for VersionValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionValidationError::TooOld { found, min } => {
+                write!(
+                    f,
+                    "remote version {found} is older than the minimum supported version {min}"
+                )
+            }
+            VersionValidationError::TooNew { found, max } => {
+                write!(f, "remote version {found} is newer than the maximum supported version {max} (unknown to this build)")
+            }
+            VersionValidationError::Unparseable { text } => {
+                write!(f, "could not parse a semver version out of {text:?}")
+            }
+        }
+    }
+}
+
+/// The range of remote versions this build is willing to accept, both
+/// bounds derived from `FALLBACK_VERSION` (this build's own Cargo.toml
+/// version) rather than hard-coded: the floor is one major version behind
+/// this build, and the ceiling is one major version ahead. That keeps the
+/// range moving with the build instead of needing a manual bump every major
+/// release, and it means a `0.x` build is never silently rejected by a
+/// stale `1.0.0`-style floor. An unexpectedly new remote major is rejected
+/// instead of silently adopted; an unexpectedly old remote major (more than
+/// one major version behind) is rejected instead of silently downgrading.
+static SUPPORTED_VERSION_RANGE: LazyLock<SupportedVersionRange> = LazyLock::new(|| {
+    let current = parse_semver(FALLBACK_VERSION).unwrap_or(SemVer {
+        major: 0,
+        minor: 0,
+        patch: 0,
+        prerelease: None,
+        build: None,
+    });
+    SupportedVersionRange {
+        min: SemVer {
+            major: current.major.saturating_sub(1),
+            minor: 0,
+            patch: 0,
+            prerelease: None,
+            build: None,
+        },
+        max: Some(SemVer {
+            major: current.major + 1,
+            minor: 0,
+            patch: 0,
+            prerelease: None,
+            build: None,
+        }),
+    }
+});
+
+/// Parse and validate a remote version string against `range`, producing a
+/// structured error that distinguishes "too old", "too new", and
+/// "unparseable" instead of a single silent fallback.
+fn validate_remote_version(
+    text: &str,
+    range: &SupportedVersionRange,
+) -> Result<SemVer, VersionValidationError> {
+    let version = parse_semver(text).ok_or_else(|| VersionValidationError::Unparseable {
+        text: text.to_string(),
+    })?;
+    range.validate(&version)?;
+    Ok(version)
+}
+
+/// Blocking HTTP fetch of the raw remote version response body. Only ever
+/// called from the background refresh thread so it never delays
+/// `USER_AGENT` init.
+fn fetch_remote_body_blocking() -> Option<String> {
+    reqwest::blocking::get(VERSION_URL).ok()?.text().ok()
+}
+
+/// Refresh the cached remote version on a background thread. A version that
+/// fails validation (or a body that fails to parse at all) is logged with
+/// exactly why it was rejected and otherwise swallowed; the next startup (or
+/// next TTL expiry) just retries.
+fn spawn_background_refresh() {
+    std::thread::spawn(|| {
+        let Some(body) = fetch_remote_body_blocking() else {
+            tracing::warn!("failed to refresh remote version from update endpoint");
+            return;
+        };
+
+        match validate_remote_version(&body, &SUPPORTED_VERSION_RANGE) {
+            Ok(version) => {
+                tracing::info!(version = %version, "remote version refreshed");
+                write_version_cache(&version.to_string());
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "rejected remote version");
+            }
+        }
+    });
+}
+
+/// Environment variable that overrides the reported version outright,
+/// independent of the remote check or Cargo.toml. Handy for pinning a
+/// version in tests or CI.
+const VERSION_OVERRIDE_ENV_VAR: &str = "ANTIGRAVITY_VERSION_OVERRIDE";
+
+/// Environment variable for reordering or disabling version sources, as a
+/// comma-separated list, e.g. `ANTIGRAVITY_VERSION_SOURCES=remote,cargo_toml`
+/// to disable the env override, or `cargo_toml` alone to force the
+/// compiled-in version.
+const VERSION_SOURCES_ENV_VAR: &str = "ANTIGRAVITY_VERSION_SOURCES";
+
+/// Default precedence: an explicit env override wins first, then the cached
+/// remote check, then the Cargo.toml fallback.
+const DEFAULT_VERSION_SOURCE_PRECEDENCE: &[VersionSource] = &[
+    VersionSource::Env,
+    VersionSource::Remote,
+    VersionSource::CargoToml,
+];
+
+/// The ordered list of sources `fetch_remote_version` walks, the first of
+/// which yields a valid, in-range semver wins. Configurable via
+/// `ANTIGRAVITY_VERSION_SOURCES` so e.g. CI can prefer the env override
+/// while production prefers remote.
+fn version_source_precedence() -> Vec<VersionSource> {
+    let Ok(raw) = std::env::var(VERSION_SOURCES_ENV_VAR) else {
+        return DEFAULT_VERSION_SOURCE_PRECEDENCE.to_vec();
+    };
+
+    let parsed: Vec<VersionSource> = raw.split(',').filter_map(VersionSource::parse).collect();
+    if parsed.is_empty() {
+        DEFAULT_VERSION_SOURCE_PRECEDENCE.to_vec()
+    } else {
+        parsed
+    }
+}
+
+/// Resolve a single source, returning `None` when that source has nothing
+/// to offer (unset env override, offline/stale-less remote cache, etc.) so
+/// the caller can fall through to the next source in the chain.
+fn resolve_version_source(source: VersionSource) -> Option<(String, VersionSource)> {
+    match source {
+        VersionSource::Env => {
+            let raw = std::env::var(VERSION_OVERRIDE_ENV_VAR).ok()?;
+            let version = validate_remote_version(&raw, &SUPPORTED_VERSION_RANGE).ok()?;
+            // Return the canonical parsed form, not the raw env value: the
+            // latter may carry stray whitespace/newlines or a `v` prefix,
+            // which would otherwise flow straight into the User-Agent
+            // header string.
+            Some((version.to_string(), VersionSource::Env))
+        }
+        VersionSource::Remote => {
+            if is_offline() {
+                return None;
+            }
+            match read_version_cache() {
+                Some(cache) if cache.is_fresh() => Some((cache.version, VersionSource::Remote)),
+                stale => {
+                    spawn_background_refresh();
+                    stale.map(|cache| (cache.version, VersionSource::Remote))
+                }
+            }
+        }
+        VersionSource::CargoToml => Some((FALLBACK_VERSION.to_string(), VersionSource::CargoToml)),
+    }
+}
+
+/// Determine the version to report by walking `version_source_precedence`
+/// in order and returning the first source that yields a value. Never
+/// blocks on the network: the remote source only reads its cache
+/// synchronously, spawning a background refresh when stale or missing.
+/// When `ANTIGRAVITY_OFFLINE` is set, the remote source never constructs an
+/// HTTP client and the chain falls through to the next source.
+///
+/// Returns `None` when every configured source comes up empty — notably,
+/// this happens if `ANTIGRAVITY_VERSION_SOURCES` is configured to exclude
+/// `cargo_toml`. Excluding a source really disables it here; `CargoToml`
+/// is not forced back in as a hidden terminal fallback. `USER_AGENT` keeps
+/// its own unconditional fallback to `FALLBACK_VERSION` on top of this, for
+/// its own unrelated need to never end up with an empty version string.
+fn fetch_remote_version() -> Option<(String, VersionSource)> {
+    version_source_precedence()
+        .into_iter()
+        .find_map(resolve_version_source)
+}
+
+/// Whether `cached`'s release (major.minor.patch) is strictly newer than
+/// `current`'s. Compares parsed releases rather than raw strings so the
+/// backend's habitual build/prerelease suffix (e.g.
+/// `1.15.8-5724687216017408`) doesn't get flagged as a newer release when
+/// it's actually the same one this build already ships, and an older or
+/// rolled-back cached release (e.g. a dev build ahead of what the remote
+/// endpoint currently serves) is never reported as an update. Returns
+/// `None` if either string doesn't parse as a semver.
+fn is_release_newer(cached: &str, current: &str) -> Option<bool> {
+    let cached = parse_semver(cached)?;
+    let current = parse_semver(current)?;
+    Some((cached.major, cached.minor, cached.patch) > (current.major, current.minor, current.patch))
+}
+
+/// Returns the cached remote version when its release is strictly newer
+/// than the version this build reports as its fallback, so callers can
+/// surface an "update available" notice instead of the result being
+/// silently discarded.
+pub fn remote_update_available() -> Option<String> {
+    let cache = read_version_cache()?;
+    is_release_newer(&cache.version, FALLBACK_VERSION)?.then_some(cache.version)
 }
 
 /// Shared User-Agent string for all upstream API requests.
 /// Format: antigravity/{version} {os}/{arch}
-/// Version priority: remote endpoint > Cargo.toml
+/// Version source precedence: see `version_source_precedence` (defaults to
+/// env override > cached remote endpoint > Cargo.toml). If the configured
+/// chain excludes every source (e.g. `cargo_toml` is excluded and nothing
+/// else resolved), the User-Agent unconditionally falls back to
+/// `FALLBACK_VERSION` anyway — it can never be configured into emitting an
+/// empty version.
 /// OS and architecture are detected at runtime.
 pub static USER_AGENT: LazyLock<String> = LazyLock::new(|| {
-    let (version, source) = fetch_remote_version();
+    let (version, source) = fetch_remote_version()
+        .unwrap_or_else(|| (FALLBACK_VERSION.to_string(), VersionSource::CargoToml));
+    // The User-Agent only ever wants the bare X.Y.Z, never a resolved
+    // source's prerelease/build tail.
+    let display_version = parse_version(&version).unwrap_or(version);
 
     tracing::info!(
-        version = %version,
+        version = %display_version,
         source = ?source,
         "User-Agent initialized"
     );
 
     format!(
         "antigravity/{} {}/{}",
-        version,
+        display_version,
         std::env::consts::OS,
         std::env::consts::ARCH
     )
@@ -59,6 +505,19 @@ pub static USER_AGENT: LazyLock<String> = LazyLock::new(|| {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::Mutex;
+
+    /// `OFFLINE_ENV_VAR`, `VERSION_OVERRIDE_ENV_VAR`, and
+    /// `VERSION_SOURCES_ENV_VAR` are real process environment variables
+    /// shared across the whole test binary, which `cargo test` runs
+    /// concurrently by default. Any test that sets/removes one of them must
+    /// hold this guard for the duration of the mutation and assertion, or
+    /// it can observe another thread's env var mid-test.
+    static ENV_VAR_TEST_GUARD: Mutex<()> = Mutex::new(());
+
+    fn lock_env_vars() -> std::sync::MutexGuard<'static, ()> {
+        ENV_VAR_TEST_GUARD.lock().unwrap_or_else(|e| e.into_inner())
+    }
 
     #[test]
     fn test_parse_version_from_updater_response() {
@@ -86,5 +545,234 @@ mod tests {
         let text = "antigravity/1.15.8 windows/amd64";
         assert_eq!(parse_version(text), Some("1.15.8".to_string()));
     }
-}
 
+    #[test]
+    fn test_version_cache_is_fresh() {
+        let cache = VersionCache {
+            version: "1.15.8".to_string(),
+            fetched_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        };
+        assert!(cache.is_fresh());
+    }
+
+    #[test]
+    fn test_version_cache_is_stale() {
+        let cache = VersionCache {
+            version: "1.15.8".to_string(),
+            fetched_at: 0,
+        };
+        assert!(!cache.is_fresh());
+    }
+
+    #[test]
+    fn test_parse_semver_prerelease() {
+        let v = parse_semver("1.2.3-rc.1").unwrap();
+        assert_eq!((v.major, v.minor, v.patch), (1, 2, 3));
+        assert_eq!(v.prerelease.as_deref(), Some("rc.1"));
+        assert_eq!(v.build, None);
+    }
+
+    #[test]
+    fn test_parse_semver_build_metadata() {
+        let v = parse_semver("1.2.3+build.5").unwrap();
+        assert_eq!(v.prerelease, None);
+        assert_eq!(v.build.as_deref(), Some("build.5"));
+    }
+
+    #[test]
+    fn test_parse_semver_upstream_suffix_as_prerelease() {
+        let v = parse_semver("1.15.8-5724687216017408").unwrap();
+        assert_eq!((v.major, v.minor, v.patch), (1, 15, 8));
+        assert_eq!(v.prerelease.as_deref(), Some("5724687216017408"));
+    }
+
+    #[test]
+    fn test_semver_ordering_ignores_build_metadata() {
+        let a = parse_semver("1.2.3+foo").unwrap();
+        let b = parse_semver("1.2.3+bar").unwrap();
+        assert_eq!(a.cmp(&b), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_semver_equality_requires_matching_build_metadata() {
+        let a = parse_semver("1.2.3+foo").unwrap();
+        let b = parse_semver("1.2.3+bar").unwrap();
+        assert_ne!(a, b);
+        assert_eq!(a, parse_semver("1.2.3+foo").unwrap());
+    }
+
+    #[test]
+    fn test_semver_release_outranks_prerelease() {
+        let release = parse_semver("1.0.0").unwrap();
+        let prerelease = parse_semver("1.0.0-alpha").unwrap();
+        assert!(release > prerelease);
+    }
+
+    #[test]
+    fn test_semver_prerelease_numeric_ordering() {
+        let alpha1 = parse_semver("1.0.0-alpha.1").unwrap();
+        let alpha2 = parse_semver("1.0.0-alpha.2").unwrap();
+        assert!(alpha1 < alpha2);
+    }
+
+    #[test]
+    fn test_is_offline_reads_env_var() {
+        let _guard = lock_env_vars();
+        std::env::set_var(OFFLINE_ENV_VAR, "1");
+        assert!(is_offline());
+        std::env::set_var(OFFLINE_ENV_VAR, "0");
+        assert!(!is_offline());
+        std::env::remove_var(OFFLINE_ENV_VAR);
+        assert!(!is_offline());
+    }
+
+    #[test]
+    fn test_fetch_remote_version_short_circuits_when_offline() {
+        let _guard = lock_env_vars();
+        std::env::set_var(OFFLINE_ENV_VAR, "1");
+        let (version, source) = fetch_remote_version().unwrap();
+        std::env::remove_var(OFFLINE_ENV_VAR);
+        assert_eq!(version, FALLBACK_VERSION);
+        assert_eq!(source, VersionSource::CargoToml);
+    }
+
+    fn test_range() -> SupportedVersionRange {
+        SupportedVersionRange {
+            min: parse_semver("1.0.0").unwrap(),
+            max: Some(parse_semver("2.0.0").unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_validate_remote_version_too_old() {
+        let err = validate_remote_version("0.9.0", &test_range()).unwrap_err();
+        assert!(matches!(err, VersionValidationError::TooOld { .. }));
+    }
+
+    #[test]
+    fn test_validate_remote_version_too_new() {
+        let err = validate_remote_version("2.5.0", &test_range()).unwrap_err();
+        assert!(matches!(err, VersionValidationError::TooNew { .. }));
+    }
+
+    #[test]
+    fn test_validate_remote_version_unparseable() {
+        let err = validate_remote_version("not a version", &test_range()).unwrap_err();
+        assert!(matches!(err, VersionValidationError::Unparseable { .. }));
+    }
+
+    #[test]
+    fn test_validate_remote_version_in_range() {
+        let version = validate_remote_version("1.15.8", &test_range()).unwrap();
+        assert_eq!((version.major, version.minor, version.patch), (1, 15, 8));
+    }
+
+    #[test]
+    fn test_version_source_parse() {
+        assert_eq!(VersionSource::parse("env"), Some(VersionSource::Env));
+        assert_eq!(VersionSource::parse("Remote"), Some(VersionSource::Remote));
+        assert_eq!(
+            VersionSource::parse("cargo_toml"),
+            Some(VersionSource::CargoToml)
+        );
+        assert_eq!(VersionSource::parse("bogus"), None);
+    }
+
+    #[test]
+    fn test_version_source_precedence_defaults_without_env() {
+        let _guard = lock_env_vars();
+        std::env::remove_var(VERSION_SOURCES_ENV_VAR);
+        assert_eq!(
+            version_source_precedence(),
+            DEFAULT_VERSION_SOURCE_PRECEDENCE.to_vec()
+        );
+    }
+
+    #[test]
+    fn test_version_source_precedence_respects_env_override() {
+        let _guard = lock_env_vars();
+        std::env::set_var(VERSION_SOURCES_ENV_VAR, "cargo_toml");
+        let precedence = version_source_precedence();
+        std::env::remove_var(VERSION_SOURCES_ENV_VAR);
+        assert_eq!(precedence, vec![VersionSource::CargoToml]);
+    }
+
+    #[test]
+    fn test_resolve_version_source_env_override() {
+        let _guard = lock_env_vars();
+        std::env::set_var(VERSION_OVERRIDE_ENV_VAR, "1.15.8");
+        let resolved = resolve_version_source(VersionSource::Env);
+        std::env::remove_var(VERSION_OVERRIDE_ENV_VAR);
+        assert_eq!(resolved, Some(("1.15.8".to_string(), VersionSource::Env)));
+    }
+
+    #[test]
+    fn test_resolve_version_source_env_override_trims_and_normalizes() {
+        let _guard = lock_env_vars();
+        std::env::set_var(VERSION_OVERRIDE_ENV_VAR, "  v1.15.8-foo \n");
+        let resolved = resolve_version_source(VersionSource::Env);
+        std::env::remove_var(VERSION_OVERRIDE_ENV_VAR);
+        assert_eq!(
+            resolved,
+            Some(("1.15.8-foo".to_string(), VersionSource::Env))
+        );
+    }
+
+    #[test]
+    fn test_resolve_version_source_env_override_unset() {
+        let _guard = lock_env_vars();
+        std::env::remove_var(VERSION_OVERRIDE_ENV_VAR);
+        assert_eq!(resolve_version_source(VersionSource::Env), None);
+    }
+
+    #[test]
+    fn test_is_release_newer_ignores_matching_release_suffix() {
+        // Same release as `current`, just with the backend's habitual
+        // build-number suffix: must not look like a newer release.
+        assert_eq!(
+            is_release_newer("1.15.8-5724687216017408", "1.15.8"),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_is_release_newer_true_for_strictly_newer_release() {
+        assert_eq!(is_release_newer("1.16.0", "1.15.8"), Some(true));
+    }
+
+    #[test]
+    fn test_is_release_newer_false_when_cached_is_older() {
+        // A dev build whose Cargo.toml version is already ahead of what the
+        // remote endpoint currently serves (or a stale/rolled-back cache
+        // entry) must never be reported as an available update.
+        assert_eq!(is_release_newer("1.14.0", "1.15.8"), Some(false));
+    }
+
+    #[test]
+    fn test_fetch_remote_version_prefers_env_override() {
+        let _guard = lock_env_vars();
+        std::env::set_var(VERSION_OVERRIDE_ENV_VAR, "1.15.8");
+        let (version, source) = fetch_remote_version().unwrap();
+        std::env::remove_var(VERSION_OVERRIDE_ENV_VAR);
+        assert_eq!(version, "1.15.8");
+        assert_eq!(source, VersionSource::Env);
+    }
+
+    #[test]
+    fn test_fetch_remote_version_honors_excluded_cargo_toml() {
+        let _guard = lock_env_vars();
+        std::env::remove_var(VERSION_OVERRIDE_ENV_VAR);
+        std::env::set_var(OFFLINE_ENV_VAR, "1");
+        std::env::set_var(VERSION_SOURCES_ENV_VAR, "env,remote");
+        let result = fetch_remote_version();
+        std::env::remove_var(OFFLINE_ENV_VAR);
+        std::env::remove_var(VERSION_SOURCES_ENV_VAR);
+        // Env unset, Remote offline, and cargo_toml deliberately excluded
+        // from the chain: every configured source came up empty, so the
+        // resolver must not silently fall back to Cargo.toml on its own.
+        assert_eq!(result, None);
+    }
+}